@@ -1,27 +1,50 @@
 #[macro_use]
 extern crate serde; // Import the serde library for serialization and deserialization
 
-use candid::{Decode, Encode}; // Import Decode and Encode from the candid library
+use candid::{Decode, Encode, Principal}; // Import Decode, Encode and Principal from the candid library
 use ic_cdk::api::time; // Import the time API from ic_cdk
+use ic_cdk_timers::TimerId; // Import the timer handle type so a running timer can be cancelled/replaced
 use ic_stable_structures::memory_manager::{MemoryId, MemoryManager, VirtualMemory}; // Import memory management structures from ic_stable_structures
 use ic_stable_structures::{BoundedStorable, Cell, DefaultMemoryImpl, StableBTreeMap, Storable}; // Import stable structures
+use std::collections::hash_map::DefaultHasher; // Import the default hasher used to turn tokens into fixed-size keys
+use std::collections::HashMap; // Import HashMap for tallying token matches during search
+use std::hash::{Hash, Hasher}; // Import Hash/Hasher so tokens can be hashed into u64s
+use std::time::Duration; // Import Duration to configure the periodic archive timer
 use std::{borrow::Cow, cell::RefCell}; // Import Cow and RefCell from the standard library
 
 type Memory = VirtualMemory<DefaultMemoryImpl>; // Type alias for VirtualMemory using DefaultMemoryImpl
 type IdCell = Cell<u64, Memory>; // Type alias for Cell storing u64 with Memory
 
+// Current schema version written by post_upgrade; bump this whenever a migration is introduced.
+const SCHEMA_VERSION: u32 = 1;
+
+// How long a book stays in the live STORAGE map before the archive timer sweeps it out.
+const ARCHIVE_RETENTION_NANOS: u64 = 365 * 24 * 60 * 60 * 1_000_000_000; // ~1 year, in nanoseconds
+
+// How often the rate-limit eviction timer sweeps stale per-caller windows out of RATE_LIMIT_STATE.
+const RATE_LIMIT_EVICTION_PERIOD_SECS: u64 = 60 * 60;
+
+// Upper bound, in bytes, on each of title/author/category. Book is a BoundedStorable with a
+// fixed MAX_SIZE, so these (along with the metadata caps below) must be bounded or an oversized
+// value would panic inside StableBTreeMap::insert instead of returning Error::InvalidInput.
+const BOOK_FIELD_MAX_LEN: usize = 256;
+
 #[derive(candid::CandidType, Clone, Serialize, Deserialize, Default)] // Derive macros for the Book struct
 struct Book {
     id: u64, // Unique identifier for the book
     title: String, // Title of the book
     author: String, // Author of the book
+    category: Option<String>, // Optional category/tag the book is filed under
     created_at: u64, // Timestamp of when the book was created
     updated_at: Option<u64>, // Optional timestamp of when the book was last updated
+    // Open typed attribute bag, validated against FIELD_SCHEMA. `opt` (not a bare vec) so that
+    // candid decodes books written before this field existed as `None` instead of trapping.
+    metadata: Option<Vec<(String, String)>>,
 }
 
 // Implement the Storable trait for the Book struct
 impl Storable for Book {
-    fn to_bytes(&self) -> std::borrow::Cow<[u8]> {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
         Cow::Owned(Encode!(self).unwrap()) // Serialize the Book struct to bytes
     }
 
@@ -32,10 +55,285 @@ impl Storable for Book {
 
 // Implement the BoundedStorable trait for the Book struct
 impl BoundedStorable for Book {
-    const MAX_SIZE: u32 = 1024; // Maximum size of the serialized Book in bytes
+    const MAX_SIZE: u32 = 4096; // Maximum size of the serialized Book in bytes (raised to fit metadata)
     const IS_FIXED_SIZE: bool = false; // Indicates that the size is not fixed
 }
 
+// Composite key (token_hash, book_id) used to store the inverted-index postings.
+// Keeping it a fixed 16-byte big-endian encoding means the postings for a single
+// token sit in a contiguous range, ordered by book_id, that `range` can scan directly.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct PostingKey {
+    token_hash: u64, // Hash of the indexed token
+    book_id: u64, // Id of the book the token appears in
+}
+
+impl Storable for PostingKey {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        let mut bytes = Vec::with_capacity(16);
+        bytes.extend_from_slice(&self.token_hash.to_be_bytes()); // Big-endian so byte order matches numeric order
+        bytes.extend_from_slice(&self.book_id.to_be_bytes());
+        Cow::Owned(bytes)
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        let token_hash = u64::from_be_bytes(bytes[0..8].try_into().unwrap());
+        let book_id = u64::from_be_bytes(bytes[8..16].try_into().unwrap());
+        PostingKey { token_hash, book_id }
+    }
+}
+
+impl BoundedStorable for PostingKey {
+    const MAX_SIZE: u32 = 16; // token_hash (8 bytes) + book_id (8 bytes)
+    const IS_FIXED_SIZE: bool = true;
+}
+
+// Maximum number of bytes of a normalized field value kept in a SecondaryIndexKey.
+// Longer values are truncated; this only affects ordering/grouping for browsing, not the
+// book data itself, which is read back in full from STORAGE.
+const SECONDARY_FIELD_MAX_LEN: usize = 64;
+
+// Composite key (normalized_field, book_id) used by the author/category secondary indexes.
+// The field is stored in a fixed-width, null-padded slot so every key is the same size and
+// all books sharing a field value sit in a contiguous range that `range` can scan.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct SecondaryIndexKey {
+    field: [u8; SECONDARY_FIELD_MAX_LEN], // Null-padded, normalized field value (author or category)
+    book_id: u64, // Id of the book with that field value
+}
+
+impl SecondaryIndexKey {
+    fn new(normalized_field: &str, book_id: u64) -> Self {
+        let mut field = [0u8; SECONDARY_FIELD_MAX_LEN];
+        let bytes = normalized_field.as_bytes();
+        let len = bytes.len().min(SECONDARY_FIELD_MAX_LEN);
+        field[..len].copy_from_slice(&bytes[..len]);
+        SecondaryIndexKey { field, book_id }
+    }
+
+    fn field_as_str(&self) -> String {
+        let end = self.field.iter().position(|&b| b == 0).unwrap_or(self.field.len());
+        String::from_utf8_lossy(&self.field[..end]).to_string()
+    }
+}
+
+impl Storable for SecondaryIndexKey {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        let mut bytes = Vec::with_capacity(SECONDARY_FIELD_MAX_LEN + 8);
+        bytes.extend_from_slice(&self.field);
+        bytes.extend_from_slice(&self.book_id.to_be_bytes());
+        Cow::Owned(bytes)
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        let mut field = [0u8; SECONDARY_FIELD_MAX_LEN];
+        field.copy_from_slice(&bytes[..SECONDARY_FIELD_MAX_LEN]);
+        let book_id = u64::from_be_bytes(
+            bytes[SECONDARY_FIELD_MAX_LEN..SECONDARY_FIELD_MAX_LEN + 8]
+                .try_into()
+                .unwrap(),
+        );
+        SecondaryIndexKey { field, book_id }
+    }
+}
+
+impl BoundedStorable for SecondaryIndexKey {
+    const MAX_SIZE: u32 = (SECONDARY_FIELD_MAX_LEN + 8) as u32;
+    const IS_FIXED_SIZE: bool = true;
+}
+
+// Normalize a field value (author or category) before it's used as an index key.
+fn normalize_field(value: &str) -> String {
+    value.trim().to_lowercase()
+}
+
+// Composite key (value, book_id) used by the created_at/updated_at ordered indexes, so
+// sorting by either timestamp is a contiguous-range scan rather than a full-map load.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct U64IndexKey {
+    value: u64, // The sorted field's value (e.g. created_at)
+    book_id: u64, // Id of the book with that value
+}
+
+impl Storable for U64IndexKey {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        let mut bytes = Vec::with_capacity(16);
+        bytes.extend_from_slice(&self.value.to_be_bytes());
+        bytes.extend_from_slice(&self.book_id.to_be_bytes());
+        Cow::Owned(bytes)
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        let value = u64::from_be_bytes(bytes[0..8].try_into().unwrap());
+        let book_id = u64::from_be_bytes(bytes[8..16].try_into().unwrap());
+        U64IndexKey { value, book_id }
+    }
+}
+
+impl BoundedStorable for U64IndexKey {
+    const MAX_SIZE: u32 = 16; // value (8 bytes) + book_id (8 bytes)
+    const IS_FIXED_SIZE: bool = true;
+}
+
+#[derive(candid::CandidType, Clone, Serialize, Deserialize, Default)] // Derive macros for the InitArg struct
+struct InitArg {
+    max_books: u64, // Cap on the number of live books; add_book rejects once it's reached (0 = unlimited)
+    archive_period_secs: u64, // How often the auto-archive timer runs
+}
+
+#[derive(candid::CandidType, Clone, Serialize, Deserialize, Default)] // Derive macros for the Config struct
+struct Config {
+    max_books: u64, // Cap on the number of live books; add_book rejects once it's reached (0 = unlimited)
+    archive_period_secs: u64, // How often the auto-archive timer runs
+    schema_version: u32, // Stable-memory schema version, bumped on post_upgrade migrations
+}
+
+// Implement the Storable trait for the Config struct
+impl Storable for Config {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+}
+
+// Implement the BoundedStorable trait for the Config struct
+impl BoundedStorable for Config {
+    const MAX_SIZE: u32 = 64; // u64 + u64 + u32 plus candid framing, generously rounded up
+    const IS_FIXED_SIZE: bool = false;
+}
+
+// How a metadata field's string value should be parsed and normalized.
+#[derive(candid::CandidType, Clone, Serialize, Deserialize)]
+enum Conversion {
+    AsIs, // No conversion: store the value verbatim
+    Integer, // Parse as a signed integer (i64)
+    Float, // Parse as a floating-point number (f64)
+    Boolean, // Parse as "true"/"false" (case-insensitive) or "1"/"0"
+    Timestamp, // Parse as an epoch-nanoseconds integer or an RFC3339 string
+    TimestampFmt(String), // Parse with an explicit chrono strftime-style format string
+}
+
+// Per-canister, stable-memory-backed schema mapping metadata field name -> Conversion.
+#[derive(candid::CandidType, Clone, Serialize, Deserialize, Default)]
+struct FieldSchema {
+    fields: Vec<(String, Conversion)>, // (field name, expected conversion)
+}
+
+// Implement the Storable trait for the FieldSchema struct
+impl Storable for FieldSchema {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+}
+
+// Implement the BoundedStorable trait for the FieldSchema struct
+impl BoundedStorable for FieldSchema {
+    const MAX_SIZE: u32 = 4096; // Generous cap on the number/length of declared fields
+    const IS_FIXED_SIZE: bool = false;
+}
+
+// Wrapper so a candid::Principal can be kept in a stable Cell.
+#[derive(Clone)]
+struct AdminPrincipal(Principal);
+
+impl Storable for AdminPrincipal {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        Cow::Owned(self.0.as_slice().to_vec())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        AdminPrincipal(Principal::from_slice(bytes.as_ref()))
+    }
+}
+
+impl BoundedStorable for AdminPrincipal {
+    const MAX_SIZE: u32 = 29; // Principal::MAX_LENGTH_IN_BYTES
+    const IS_FIXED_SIZE: bool = false;
+}
+
+impl Default for AdminPrincipal {
+    fn default() -> Self {
+        AdminPrincipal(Principal::anonymous())
+    }
+}
+
+// Sliding-window rate-limit policy applied to every mutating endpoint.
+#[derive(candid::CandidType, Clone, Serialize, Deserialize, Default)]
+struct RateLimitRule {
+    max_ops: u32, // Maximum update calls a caller may make within window_secs
+    window_secs: u64, // Length of the sliding window, in seconds
+}
+
+// Implement the Storable trait for the RateLimitRule struct
+impl Storable for RateLimitRule {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+}
+
+// Implement the BoundedStorable trait for the RateLimitRule struct
+impl BoundedStorable for RateLimitRule {
+    const MAX_SIZE: u32 = 64; // u32 + u64 plus candid framing, generously rounded up
+    const IS_FIXED_SIZE: bool = false;
+}
+
+// Wrapper so a candid::Principal can be used as a StableBTreeMap key.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct PrincipalKey(Principal);
+
+impl Storable for PrincipalKey {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        Cow::Owned(self.0.as_slice().to_vec())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        PrincipalKey(Principal::from_slice(bytes.as_ref()))
+    }
+}
+
+impl BoundedStorable for PrincipalKey {
+    const MAX_SIZE: u32 = 29; // Principal::MAX_LENGTH_IN_BYTES
+    const IS_FIXED_SIZE: bool = false;
+}
+
+// A caller's current sliding-window rate-limit record.
+#[derive(Clone)]
+struct RateWindow {
+    window_start: u64, // Start of the current window, in seconds since the epoch
+    count: u32, // Number of update calls made by this caller within the current window
+}
+
+impl Storable for RateWindow {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        let mut bytes = Vec::with_capacity(12);
+        bytes.extend_from_slice(&self.window_start.to_be_bytes());
+        bytes.extend_from_slice(&self.count.to_be_bytes());
+        Cow::Owned(bytes)
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        let window_start = u64::from_be_bytes(bytes[0..8].try_into().unwrap());
+        let count = u32::from_be_bytes(bytes[8..12].try_into().unwrap());
+        RateWindow { window_start, count }
+    }
+}
+
+impl BoundedStorable for RateWindow {
+    const MAX_SIZE: u32 = 12; // window_start (8 bytes) + count (4 bytes)
+    const IS_FIXED_SIZE: bool = true;
+}
+
 thread_local! {
     // Thread-local storage for memory manager
     static MEMORY_MANAGER: RefCell<MemoryManager<DefaultMemoryImpl>> = RefCell::new(
@@ -53,12 +351,252 @@ thread_local! {
         RefCell::new(StableBTreeMap::init(
             MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(1)))
     ));
+
+    // Thread-local storage for the full-text search inverted index: token_hash/book_id -> ()
+    static SEARCH_INDEX: RefCell<StableBTreeMap<PostingKey, (), Memory>> =
+        RefCell::new(StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(2)))
+    ));
+
+    // Thread-local storage for the secondary index browsable by author
+    static AUTHOR_INDEX: RefCell<StableBTreeMap<SecondaryIndexKey, (), Memory>> =
+        RefCell::new(StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(3)))
+    ));
+
+    // Thread-local storage for the secondary index browsable by category
+    static CATEGORY_INDEX: RefCell<StableBTreeMap<SecondaryIndexKey, (), Memory>> =
+        RefCell::new(StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(4)))
+    ));
+
+    // Thread-local storage for the ordered index used by list_books(sort: ByTitle)
+    static TITLE_INDEX: RefCell<StableBTreeMap<SecondaryIndexKey, (), Memory>> =
+        RefCell::new(StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(5)))
+    ));
+
+    // Thread-local storage for the ordered index used by list_books(sort: ByCreatedAt)
+    static CREATED_AT_INDEX: RefCell<StableBTreeMap<U64IndexKey, (), Memory>> =
+        RefCell::new(StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(6)))
+    ));
+
+    // Thread-local storage for the ordered index used by list_books(sort: ByUpdatedAt)
+    static UPDATED_AT_INDEX: RefCell<StableBTreeMap<U64IndexKey, (), Memory>> =
+        RefCell::new(StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(7)))
+    ));
+
+    // Thread-local storage for the total book count, kept in sync on insert/delete
+    static TOTAL_BOOKS: RefCell<IdCell> = RefCell::new(
+        IdCell::init(MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(8))), 0)
+            .expect("Cannot create a counter")
+    );
+
+    // Thread-local storage for the canister's versioned configuration
+    static CONFIG: RefCell<Cell<Config, Memory>> = RefCell::new(
+        Cell::init(MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(9))), Config::default())
+            .expect("Cannot create the config cell")
+    );
+
+    // Thread-local storage for the admin principal allowed to change the configuration
+    static ADMIN: RefCell<Cell<AdminPrincipal, Memory>> = RefCell::new(
+        Cell::init(MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(10))), AdminPrincipal::default())
+            .expect("Cannot create the admin cell")
+    );
+
+    // Thread-local storage for books swept out of STORAGE by the auto-archive timer
+    static ARCHIVED: RefCell<StableBTreeMap<u64, Book, Memory>> =
+        RefCell::new(StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(11)))
+    ));
+
+    // In-memory (non-persisted) handle to the running archive timer, so it can be replaced
+    static ARCHIVE_TIMER: RefCell<Option<TimerId>> = const { RefCell::new(None) };
+
+    // Thread-local storage for the metadata field-name -> Conversion validation schema
+    static FIELD_SCHEMA: RefCell<Cell<FieldSchema, Memory>> = RefCell::new(
+        Cell::init(MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(12))), FieldSchema::default())
+            .expect("Cannot create the field schema cell")
+    );
+
+    // Thread-local storage for the rate-limit policy applied to update endpoints
+    static RATE_LIMIT_RULE: RefCell<Cell<RateLimitRule, Memory>> = RefCell::new(
+        Cell::init(MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(13))), RateLimitRule::default())
+            .expect("Cannot create the rate limit rule cell")
+    );
+
+    // Thread-local storage for each caller's current sliding-window rate-limit record
+    static RATE_LIMIT_STATE: RefCell<StableBTreeMap<PrincipalKey, RateWindow, Memory>> =
+        RefCell::new(StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(14)))
+    ));
+
+    // In-memory (non-persisted) handle to the running rate-limit eviction timer
+    static RATE_LIMIT_EVICTION_TIMER: RefCell<Option<TimerId>> = const { RefCell::new(None) };
+}
+
+// Common short words that add noise to the index without helping search relevance.
+const STOPWORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "by", "for", "from", "in", "into", "is", "it",
+    "of", "on", "or", "that", "the", "to", "with",
+];
+
+// Split text into lowercase alphanumeric tokens, dropping stopwords, for both indexing and querying.
+fn tokenize(text: &str) -> Vec<String> {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|token| !token.is_empty() && !STOPWORDS.contains(token))
+        .map(|token| token.to_string())
+        .collect()
+}
+
+// Hash a token into the u64 used as the first half of a PostingKey.
+fn hash_token(token: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    token.hash(&mut hasher);
+    hasher.finish()
+}
+
+// Tokens indexed for a book: its title and author words combined.
+fn index_tokens(book: &Book) -> Vec<String> {
+    let mut tokens = tokenize(&book.title);
+    tokens.extend(tokenize(&book.author));
+    tokens
+}
+
+// Add postings for every token of a book to the search index.
+fn index_book(book: &Book) {
+    SEARCH_INDEX.with(|index| {
+        let mut index = index.borrow_mut();
+        for token in index_tokens(book) {
+            index.insert(
+                PostingKey {
+                    token_hash: hash_token(&token),
+                    book_id: book.id,
+                },
+                (),
+            );
+        }
+    });
+}
+
+// Remove postings for every token of a book from the search index.
+fn deindex_book(book: &Book) {
+    SEARCH_INDEX.with(|index| {
+        let mut index = index.borrow_mut();
+        for token in index_tokens(book) {
+            index.remove(&PostingKey {
+                token_hash: hash_token(&token),
+                book_id: book.id,
+            });
+        }
+    });
+}
+
+// Add a book's author/category entries to the secondary indexes.
+fn index_secondary(book: &Book) {
+    AUTHOR_INDEX.with(|index| {
+        index.borrow_mut().insert(
+            SecondaryIndexKey::new(&normalize_field(&book.author), book.id),
+            (),
+        );
+    });
+    if let Some(category) = &book.category {
+        CATEGORY_INDEX.with(|index| {
+            index.borrow_mut().insert(
+                SecondaryIndexKey::new(&normalize_field(category), book.id),
+                (),
+            );
+        });
+    }
+}
+
+// Remove a book's author/category entries from the secondary indexes.
+fn deindex_secondary(book: &Book) {
+    AUTHOR_INDEX.with(|index| {
+        index
+            .borrow_mut()
+            .remove(&SecondaryIndexKey::new(&normalize_field(&book.author), book.id));
+    });
+    if let Some(category) = &book.category {
+        CATEGORY_INDEX.with(|index| {
+            index
+                .borrow_mut()
+                .remove(&SecondaryIndexKey::new(&normalize_field(category), book.id));
+        });
+    }
+}
+
+// Add a book's entries to the ordered title/created_at/updated_at indexes used for sorted listing.
+fn index_sort_keys(book: &Book) {
+    TITLE_INDEX.with(|index| {
+        index.borrow_mut().insert(
+            SecondaryIndexKey::new(&normalize_field(&book.title), book.id),
+            (),
+        );
+    });
+    CREATED_AT_INDEX.with(|index| {
+        index.borrow_mut().insert(
+            U64IndexKey {
+                value: book.created_at,
+                book_id: book.id,
+            },
+            (),
+        );
+    });
+    UPDATED_AT_INDEX.with(|index| {
+        index.borrow_mut().insert(
+            U64IndexKey {
+                value: book.updated_at.unwrap_or(0),
+                book_id: book.id,
+            },
+            (),
+        );
+    });
+}
+
+// Remove a book's entries from the ordered title/created_at/updated_at indexes.
+fn deindex_sort_keys(book: &Book) {
+    TITLE_INDEX.with(|index| {
+        index
+            .borrow_mut()
+            .remove(&SecondaryIndexKey::new(&normalize_field(&book.title), book.id));
+    });
+    CREATED_AT_INDEX.with(|index| {
+        index.borrow_mut().remove(&U64IndexKey {
+            value: book.created_at,
+            book_id: book.id,
+        });
+    });
+    UPDATED_AT_INDEX.with(|index| {
+        index.borrow_mut().remove(&U64IndexKey {
+            value: book.updated_at.unwrap_or(0),
+            book_id: book.id,
+        });
+    });
+}
+
+// List the books stored under a normalized secondary-index field value.
+fn list_by_secondary_index(
+    index: &StableBTreeMap<SecondaryIndexKey, (), Memory>,
+    normalized_field: &str,
+) -> Vec<Book> {
+    let start = SecondaryIndexKey::new(normalized_field, 0);
+    let end = SecondaryIndexKey::new(normalized_field, u64::MAX);
+    index
+        .range(start..=end)
+        .filter_map(|(key, _)| _get_book(&key.book_id))
+        .collect()
 }
 
 #[derive(candid::CandidType, Serialize, Deserialize, Default)] // Derive macros for the BookPayload struct
 struct BookPayload {
     title: String, // Title of the book
     author: String, // Author of the book
+    category: Option<String>, // Optional category/tag the book is filed under
+    metadata: Vec<(String, String)>, // Open typed attribute bag, validated against FIELD_SCHEMA
 }
 
 #[ic_cdk::query] // Mark the function as a query method
@@ -73,9 +611,22 @@ fn get_book(id: u64) -> Result<Book, Error> {
 
 #[ic_cdk::update] // Mark the function as an update method
 fn add_book(book: BookPayload) -> Result<Book, Error>  {
+    check_rate_limit()?;
     if book.title.is_empty() || book.author.is_empty() {
         return Err(Error::InvalidInput { msg: "All fields must be provided and non-empty".to_string() });
     }
+    validate_book_field_lengths(&book.title, &book.author, &book.category)?;
+    let metadata = validate_metadata(&book.metadata)?;
+
+    let max_books = CONFIG.with(|c| c.borrow().get().max_books);
+    if max_books > 0 {
+        let total = TOTAL_BOOKS.with(|counter| *counter.borrow().get());
+        if total >= max_books {
+            return Err(Error::InvalidInput {
+                msg: format!("book limit of {} reached", max_books),
+            });
+        }
+    }
 
     // Increment the ID counter
     let id = ID_COUNTER
@@ -90,27 +641,60 @@ fn add_book(book: BookPayload) -> Result<Book, Error>  {
         id,
         title: book.title,
         author: book.author,
+        category: book.category,
         created_at: time(),
         updated_at: None,
+        metadata: Some(metadata),
     };
 
     // Insert the new book into storage
     do_insert(&book);
+    index_book(&book); // Add the new book's title/author tokens to the search index
 
     Ok(book)
 }
 
 #[ic_cdk::update] // Mark the function as an update method
 fn update_book(id: u64, payload: BookPayload) -> Result<Book, Error> {
+    check_rate_limit()?;
     if payload.title.is_empty() || payload.author.is_empty() {
         return Err(Error::InvalidInput { msg: "All fields must be provided and non-empty".to_string() });
     }
+    validate_book_field_lengths(&payload.title, &payload.author, &payload.category)?;
+    let metadata = validate_metadata(&payload.metadata)?;
 
     match STORAGE.with(|service| service.borrow().get(&id)) {
-        Some(mut book) => {
+        Some(old_book) => {
+            let mut book = old_book.clone();
             book.title = payload.title;
             book.author = payload.author;
+            book.category = payload.category;
             book.updated_at = Some(time());
+            book.metadata = Some(metadata);
+
+            // Diff old vs. new tokens so stale postings don't linger in the search index
+            let old_tokens: std::collections::HashSet<String> =
+                index_tokens(&old_book).into_iter().collect();
+            let new_tokens: std::collections::HashSet<String> =
+                index_tokens(&book).into_iter().collect();
+            SEARCH_INDEX.with(|index| {
+                let mut index = index.borrow_mut();
+                for token in old_tokens.difference(&new_tokens) {
+                    index.remove(&PostingKey {
+                        token_hash: hash_token(token),
+                        book_id: book.id,
+                    });
+                }
+                for token in new_tokens.difference(&old_tokens) {
+                    index.insert(
+                        PostingKey {
+                            token_hash: hash_token(token),
+                            book_id: book.id,
+                        },
+                        (),
+                    );
+                }
+            });
 
             // Update the book in storage
             do_insert(&book);
@@ -125,23 +709,549 @@ fn update_book(id: u64, payload: BookPayload) -> Result<Book, Error> {
 
 // Helper method to perform insert operation
 fn do_insert(book: &Book) {
+    // Drop the old book's index entries (if any) before writing the new ones, so no
+    // secondary/sort index ever holds stale entries for this id.
+    match _get_book(&book.id) {
+        Some(old_book) => {
+            deindex_secondary(&old_book);
+            deindex_sort_keys(&old_book);
+        }
+        None => {
+            TOTAL_BOOKS
+                .with(|counter| {
+                    let current_value = *counter.borrow().get();
+                    counter.borrow_mut().set(current_value + 1)
+                })
+                .expect("cannot increment total books counter");
+        }
+    }
     STORAGE.with(|service| service.borrow_mut().insert(book.id, book.clone()));
+    index_secondary(book);
+    index_sort_keys(book);
 }
 
 #[ic_cdk::update] // Mark the function as an update method
 fn delete_book(id: u64) -> Result<Book, Error> {
+    check_rate_limit()?;
     match STORAGE.with(|service| service.borrow_mut().remove(&id)) {
-        Some(book) => Ok(book), // Return the deleted book if found
+        Some(book) => {
+            deindex_book(&book); // Purge all postings for the deleted book
+            deindex_secondary(&book); // Purge the author/category secondary-index entries
+            deindex_sort_keys(&book); // Purge the title/created_at/updated_at sort-index entries
+            TOTAL_BOOKS
+                .with(|counter| {
+                    let current_value = *counter.borrow().get();
+                    counter.borrow_mut().set(current_value - 1)
+                })
+                .expect("cannot decrement total books counter");
+            Ok(book)
+        }
         None => Err(Error::NotFound {
             msg: format!("couldn't delete a book with id={}. book not found.", id),
         }),
     }
 }
 
+#[ic_cdk::query] // Mark the function as a query method
+fn search_books(query: String, limit: u64) -> Vec<Book> {
+    let tokens = tokenize(&query);
+    if tokens.is_empty() {
+        return Vec::new();
+    }
+
+    // Tally how many query tokens match each book id across the postings ranges
+    let mut matches: HashMap<u64, u32> = HashMap::new();
+    SEARCH_INDEX.with(|index| {
+        let index = index.borrow();
+        for token in &tokens {
+            let token_hash = hash_token(token);
+            let start = PostingKey {
+                token_hash,
+                book_id: 0,
+            };
+            let end = PostingKey {
+                token_hash,
+                book_id: u64::MAX,
+            };
+            for (key, _) in index.range(start..=end) {
+                *matches.entry(key.book_id).or_insert(0) += 1;
+            }
+        }
+    });
+
+    // Intersect the per-token postings: only ids that matched every query token qualify.
+    let required = tokens.len() as u32;
+    let mut ranked: Vec<u64> = matches
+        .into_iter()
+        .filter(|(_, count)| *count == required)
+        .map(|(id, _)| id)
+        .collect();
+    ranked.sort_unstable();
+
+    ranked
+        .into_iter()
+        .take(limit as usize)
+        .filter_map(|id| _get_book(&id))
+        .collect()
+}
+
+#[derive(candid::CandidType, Clone, Copy, Serialize, Deserialize)] // Derive macros for the SortKey enum
+#[allow(clippy::enum_variant_names)] // "By"-prefixed variants read clearly at call sites (sort: ByTitle)
+enum SortKey {
+    ById, // Sort by the book's id, ascending
+    ByTitle, // Sort alphabetically by (normalized) title
+    ByCreatedAt, // Sort by creation timestamp, ascending
+    ByUpdatedAt, // Sort by last-update timestamp, ascending (never-updated books sort first)
+}
+
+#[derive(candid::CandidType, Serialize, Deserialize)] // Derive macros for the Page struct
+struct Page {
+    items: Vec<Book>, // The page's books, in the requested sort order
+    total: u64, // Total number of books in the collection
+    next_offset: u64, // Offset to pass in to fetch the following page
+}
+
+#[ic_cdk::query] // Mark the function as a query method
+fn list_books(offset: u64, limit: u64, sort: SortKey) -> Page {
+    let total = TOTAL_BOOKS.with(|counter| *counter.borrow().get());
+
+    let items: Vec<Book> = match sort {
+        SortKey::ById => STORAGE.with(|service| {
+            service
+                .borrow()
+                .iter()
+                .skip(offset as usize)
+                .take(limit as usize)
+                .map(|(_, book)| book)
+                .collect()
+        }),
+        SortKey::ByTitle => TITLE_INDEX.with(|index| {
+            index
+                .borrow()
+                .iter()
+                .skip(offset as usize)
+                .take(limit as usize)
+                .filter_map(|(key, _)| _get_book(&key.book_id))
+                .collect()
+        }),
+        SortKey::ByCreatedAt => CREATED_AT_INDEX.with(|index| {
+            index
+                .borrow()
+                .iter()
+                .skip(offset as usize)
+                .take(limit as usize)
+                .filter_map(|(key, _)| _get_book(&key.book_id))
+                .collect()
+        }),
+        SortKey::ByUpdatedAt => UPDATED_AT_INDEX.with(|index| {
+            index
+                .borrow()
+                .iter()
+                .skip(offset as usize)
+                .take(limit as usize)
+                .filter_map(|(key, _)| _get_book(&key.book_id))
+                .collect()
+        }),
+    };
+
+    let next_offset = offset + items.len() as u64;
+    Page {
+        items,
+        total,
+        next_offset,
+    }
+}
+
+#[ic_cdk::query] // Mark the function as a query method
+fn list_books_by_category(name: String) -> Vec<Book> {
+    CATEGORY_INDEX.with(|index| list_by_secondary_index(&index.borrow(), &normalize_field(&name)))
+}
+
+#[ic_cdk::query] // Mark the function as a query method
+fn list_books_by_author(author: String) -> Vec<Book> {
+    AUTHOR_INDEX.with(|index| list_by_secondary_index(&index.borrow(), &normalize_field(&author)))
+}
+
+#[ic_cdk::query] // Mark the function as a query method
+fn list_categories() -> Vec<String> {
+    CATEGORY_INDEX.with(|index| {
+        let index = index.borrow();
+        let mut categories = Vec::new();
+        let mut last: Option<String> = None;
+        for (key, _) in index.iter() {
+            let field = key.field_as_str();
+            if last.as_deref() != Some(field.as_str()) {
+                categories.push(field.clone());
+                last = Some(field);
+            }
+        }
+        categories
+    })
+}
+
+#[ic_cdk::init] // Mark the function as the canister's init entry point
+fn init(init_arg: InitArg) {
+    ADMIN.with(|admin| admin.borrow_mut().set(AdminPrincipal(ic_cdk::caller())))
+        .expect("cannot set admin principal");
+    let config = Config {
+        max_books: init_arg.max_books,
+        archive_period_secs: init_arg.archive_period_secs,
+        schema_version: SCHEMA_VERSION,
+    };
+    CONFIG
+        .with(|c| c.borrow_mut().set(config))
+        .expect("cannot set initial config");
+    start_archive_timer();
+    start_rate_limit_eviction_timer();
+}
+
+#[ic_cdk::pre_upgrade] // Mark the function as the canister's pre_upgrade hook
+fn pre_upgrade() {
+    // Nothing to stash here: STORAGE, CONFIG and the other stable structures already live in
+    // stable memory via the MemoryManager, so they survive the upgrade on their own.
+}
+
+#[ic_cdk::post_upgrade] // Mark the function as the canister's post_upgrade hook
+fn post_upgrade() {
+    // Canisters deployed before ADMIN existed come back from upgrade with it still at its
+    // default (anonymous), which would permanently lock every admin-only endpoint. Claim it
+    // for the caller performing the upgrade in that case; a canister that already has a real
+    // admin is left untouched.
+    if ADMIN.with(|admin| admin.borrow().get().0) == Principal::anonymous() {
+        ADMIN
+            .with(|admin| admin.borrow_mut().set(AdminPrincipal(ic_cdk::caller())))
+            .expect("cannot bootstrap admin principal");
+    }
+
+    let mut config = CONFIG.with(|c| c.borrow().get().clone());
+    if config.schema_version < SCHEMA_VERSION {
+        // The search/secondary/sort indexes and TOTAL_BOOKS are new as of this version and
+        // start out empty on upgrade, even though STORAGE already holds books from before it.
+        // Rebuild them from STORAGE once, on the migration that introduces them.
+        backfill_indexes();
+        config.schema_version = SCHEMA_VERSION; // Bump so future migrations can branch on this
+        CONFIG
+            .with(|c| c.borrow_mut().set(config))
+            .expect("cannot persist migrated config");
+    }
+    start_archive_timer();
+    start_rate_limit_eviction_timer();
+}
+
+// Rebuild SEARCH_INDEX, AUTHOR_INDEX/CATEGORY_INDEX, the sort indexes and TOTAL_BOOKS from
+// the books already in STORAGE. Only safe to call against empty indexes (e.g. once per
+// schema migration, guarded by SCHEMA_VERSION), since it doesn't clear anything first.
+fn backfill_indexes() {
+    let mut total: u64 = 0;
+    STORAGE.with(|service| {
+        for (_, book) in service.borrow().iter() {
+            index_book(&book);
+            index_secondary(&book);
+            index_sort_keys(&book);
+            total += 1;
+        }
+    });
+    TOTAL_BOOKS
+        .with(|counter| counter.borrow_mut().set(total))
+        .expect("cannot set total books counter");
+}
+
+#[ic_cdk::query] // Mark the function as a query method
+fn get_config() -> Config {
+    CONFIG.with(|c| c.borrow().get().clone())
+}
+
+#[ic_cdk::update] // Mark the function as an update method
+fn set_config(init_arg: InitArg) -> Result<Config, Error> {
+    if ic_cdk::caller() != ADMIN.with(|admin| admin.borrow().get().0) {
+        return Err(Error::InvalidInput {
+            msg: "only the admin principal may change the configuration".to_string(),
+        });
+    }
+
+    let config = Config {
+        max_books: init_arg.max_books,
+        archive_period_secs: init_arg.archive_period_secs,
+        schema_version: SCHEMA_VERSION,
+    };
+    CONFIG
+        .with(|c| c.borrow_mut().set(config.clone()))
+        .expect("cannot persist config");
+    start_archive_timer(); // Restart so a changed archive_period_secs takes effect immediately
+
+    Ok(config)
+}
+
+// (Re)start the periodic auto-archive timer, cancelling any timer already running.
+fn start_archive_timer() {
+    ARCHIVE_TIMER.with(|timer| {
+        if let Some(old_timer) = timer.borrow_mut().take() {
+            ic_cdk_timers::clear_timer(old_timer);
+        }
+    });
+
+    let period_secs = CONFIG.with(|c| c.borrow().get().archive_period_secs);
+    if period_secs == 0 {
+        // Archiving disabled: leave the timer cleared above instead of scheduling
+        // a zero-interval timer that would fire continuously and burn cycles.
+        return;
+    }
+    let new_timer = ic_cdk_timers::set_timer_interval(Duration::from_secs(period_secs), archive_old_books);
+    ARCHIVE_TIMER.with(|timer| *timer.borrow_mut() = Some(new_timer));
+}
+
+// Move books older than the retention threshold from STORAGE into ARCHIVED, keeping the live
+// map small. Scrubs the book from every index too, the same way delete_book does.
+fn archive_old_books() {
+    let now = time();
+    let stale_ids: Vec<u64> = STORAGE.with(|service| {
+        service
+            .borrow()
+            .iter()
+            .filter(|(_, book)| now.saturating_sub(book.created_at) > ARCHIVE_RETENTION_NANOS)
+            .map(|(id, _)| id)
+            .collect()
+    });
+
+    for id in stale_ids {
+        if let Some(book) = STORAGE.with(|service| service.borrow_mut().remove(&id)) {
+            deindex_book(&book);
+            deindex_secondary(&book);
+            deindex_sort_keys(&book);
+            TOTAL_BOOKS
+                .with(|counter| {
+                    let current_value = *counter.borrow().get();
+                    counter.borrow_mut().set(current_value - 1)
+                })
+                .expect("cannot decrement total books counter");
+            ARCHIVED.with(|archived| archived.borrow_mut().insert(book.id, book));
+        }
+    }
+}
+
+#[ic_cdk::query] // Mark the function as a query method
+fn get_field_schema() -> Vec<(String, Conversion)> {
+    FIELD_SCHEMA.with(|schema| schema.borrow().get().fields.clone())
+}
+
+#[ic_cdk::update] // Mark the function as an update method
+fn set_field_schema(fields: Vec<(String, Conversion)>) -> Result<(), Error> {
+    if ic_cdk::caller() != ADMIN.with(|admin| admin.borrow().get().0) {
+        return Err(Error::InvalidInput {
+            msg: "only the admin principal may change the field schema".to_string(),
+        });
+    }
+
+    FIELD_SCHEMA
+        .with(|schema| schema.borrow_mut().set(FieldSchema { fields }))
+        .expect("cannot persist field schema");
+
+    Ok(())
+}
+
+// Parse and normalize a single metadata value against its declared Conversion.
+fn convert_metadata_value(field: &str, value: &str, conversion: &Conversion) -> Result<String, Error> {
+    let invalid = |target: &str| Error::InvalidInput {
+        msg: format!("metadata field '{}' is not a valid {}: \"{}\"", field, target, value),
+    };
+
+    match conversion {
+        Conversion::AsIs => Ok(value.to_string()),
+        Conversion::Integer => value
+            .trim()
+            .parse::<i64>()
+            .map(|v| v.to_string())
+            .map_err(|_| invalid("integer")),
+        Conversion::Float => value
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .filter(|v| v.is_finite())
+            .map(|v| v.to_string())
+            .ok_or_else(|| invalid("float")),
+        Conversion::Boolean => match value.trim().to_lowercase().as_str() {
+            "true" | "1" => Ok("true".to_string()),
+            "false" | "0" => Ok("false".to_string()),
+            _ => Err(invalid("boolean")),
+        },
+        Conversion::Timestamp => value
+            .trim()
+            .parse::<u64>()
+            .map(|nanos| nanos.to_string())
+            .or_else(|_| {
+                chrono::DateTime::parse_from_rfc3339(value.trim())
+                    .ok()
+                    .and_then(|dt| dt.timestamp_nanos_opt())
+                    .map(|nanos| nanos.to_string())
+                    .ok_or_else(|| invalid("timestamp (epoch nanoseconds or RFC3339)"))
+            }),
+        Conversion::TimestampFmt(fmt) => chrono::NaiveDateTime::parse_from_str(value.trim(), fmt)
+            .ok()
+            .and_then(|dt| dt.and_utc().timestamp_nanos_opt())
+            .map(|nanos| nanos.to_string())
+            .ok_or_else(|| invalid(&format!("timestamp matching format \"{}\"", fmt))),
+    }
+}
+
+// Upper bound on the number of metadata pairs a book may carry.
+const METADATA_MAX_PAIRS: usize = 32;
+
+// Upper bound on the combined byte length of all metadata field names and values. Chosen to
+// leave headroom within Book::MAX_SIZE (4096) for id/title/author/category/timestamps and
+// candid encoding overhead. Title/author/category are bounded separately (see BOOK_FIELD_MAX_LEN
+// in add_book/update_book) — together these keep a validated book within Book::MAX_SIZE so it
+// can never fail to encode inside StableBTreeMap::insert.
+const METADATA_MAX_TOTAL_BYTES: usize = 2048;
+
+// Reject title/author/category longer than BOOK_FIELD_MAX_LEN, since Book is a
+// BoundedStorable with a fixed MAX_SIZE and these fields are otherwise unbounded.
+fn validate_book_field_lengths(title: &str, author: &str, category: &Option<String>) -> Result<(), Error> {
+    let too_long = title.len() > BOOK_FIELD_MAX_LEN
+        || author.len() > BOOK_FIELD_MAX_LEN
+        || category.as_ref().is_some_and(|c| c.len() > BOOK_FIELD_MAX_LEN);
+    if too_long {
+        return Err(Error::InvalidInput {
+            msg: format!("title, author and category must each be at most {} bytes", BOOK_FIELD_MAX_LEN),
+        });
+    }
+    Ok(())
+}
+
+// Validate and normalize a book's metadata against FIELD_SCHEMA. Fields with no declared
+// conversion pass through unchanged, so canisters can hold arbitrary typed attributes without
+// hardcoding new struct fields. Also enforces a size cap, since Book is a BoundedStorable with
+// a fixed MAX_SIZE and an oversized metadata payload would otherwise panic on insert.
+fn validate_metadata(metadata: &[(String, String)]) -> Result<Vec<(String, String)>, Error> {
+    if metadata.len() > METADATA_MAX_PAIRS {
+        return Err(Error::InvalidInput {
+            msg: format!("metadata cannot have more than {} fields", METADATA_MAX_PAIRS),
+        });
+    }
+
+    let schema: HashMap<String, Conversion> = FIELD_SCHEMA
+        .with(|schema| schema.borrow().get().fields.clone())
+        .into_iter()
+        .collect();
+
+    let converted: Vec<(String, String)> = metadata
+        .iter()
+        .map(|(field, value)| match schema.get(field) {
+            Some(conversion) => {
+                convert_metadata_value(field, value, conversion).map(|normalized| (field.clone(), normalized))
+            }
+            None => Ok((field.clone(), value.clone())),
+        })
+        .collect::<Result<_, Error>>()?;
+
+    let total_bytes: usize = converted.iter().map(|(field, value)| field.len() + value.len()).sum();
+    if total_bytes > METADATA_MAX_TOTAL_BYTES {
+        return Err(Error::InvalidInput {
+            msg: format!("metadata cannot exceed {} bytes total", METADATA_MAX_TOTAL_BYTES),
+        });
+    }
+
+    Ok(converted)
+}
+
+#[ic_cdk::query] // Mark the function as a query method
+fn get_rate_limit() -> RateLimitRule {
+    RATE_LIMIT_RULE.with(|rule| rule.borrow().get().clone())
+}
+
+#[ic_cdk::update] // Mark the function as an update method
+fn set_rate_limit(rule: RateLimitRule) -> Result<RateLimitRule, Error> {
+    if ic_cdk::caller() != ADMIN.with(|admin| admin.borrow().get().0) {
+        return Err(Error::InvalidInput {
+            msg: "only the admin principal may change the rate limit policy".to_string(),
+        });
+    }
+
+    RATE_LIMIT_RULE
+        .with(|r| r.borrow_mut().set(rule.clone()))
+        .expect("cannot persist rate limit rule");
+
+    Ok(rule)
+}
+
+// Enforce the configured per-caller sliding-window rate limit. Called at the top of every
+// mutating endpoint. A rule with max_ops == 0 and window_secs == 0 (the default) means
+// rate limiting hasn't been configured yet, so every call is allowed through.
+fn check_rate_limit() -> Result<(), Error> {
+    let rule = RATE_LIMIT_RULE.with(|r| r.borrow().get().clone());
+    if rule.max_ops == 0 && rule.window_secs == 0 {
+        return Ok(());
+    }
+
+    let caller = PrincipalKey(ic_cdk::caller());
+    let now_secs = time() / 1_000_000_000;
+
+    let mut window = RATE_LIMIT_STATE
+        .with(|state| state.borrow().get(&caller))
+        .unwrap_or(RateWindow {
+            window_start: now_secs,
+            count: 0,
+        });
+
+    if now_secs.saturating_sub(window.window_start) >= rule.window_secs {
+        window.window_start = now_secs;
+        window.count = 0;
+    }
+    window.count += 1;
+
+    RATE_LIMIT_STATE.with(|state| state.borrow_mut().insert(caller, window.clone()));
+
+    if window.count > rule.max_ops {
+        let retry_after_secs = rule
+            .window_secs
+            .saturating_sub(now_secs.saturating_sub(window.window_start));
+        return Err(Error::RateLimited { retry_after_secs });
+    }
+
+    Ok(())
+}
+
+// (Re)start the periodic rate-limit eviction timer, cancelling any timer already running.
+fn start_rate_limit_eviction_timer() {
+    RATE_LIMIT_EVICTION_TIMER.with(|timer| {
+        if let Some(old_timer) = timer.borrow_mut().take() {
+            ic_cdk_timers::clear_timer(old_timer);
+        }
+    });
+
+    let new_timer = ic_cdk_timers::set_timer_interval(
+        Duration::from_secs(RATE_LIMIT_EVICTION_PERIOD_SECS),
+        evict_stale_rate_limit_entries,
+    );
+    RATE_LIMIT_EVICTION_TIMER.with(|timer| *timer.borrow_mut() = Some(new_timer));
+}
+
+// Drop rate-limit windows that closed at least one full window ago, to bound storage growth.
+fn evict_stale_rate_limit_entries() {
+    let rule = RATE_LIMIT_RULE.with(|r| r.borrow().get().clone());
+    let now_secs = time() / 1_000_000_000;
+
+    let stale_callers: Vec<PrincipalKey> = RATE_LIMIT_STATE.with(|state| {
+        state
+            .borrow()
+            .iter()
+            .filter(|(_, window)| now_secs.saturating_sub(window.window_start) >= rule.window_secs * 2)
+            .map(|(caller, _)| caller)
+            .collect()
+    });
+
+    RATE_LIMIT_STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        for caller in stale_callers {
+            state.remove(&caller);
+        }
+    });
+}
+
 #[derive(candid::CandidType, Deserialize, Serialize)] // Derive macros for the Error enum
 enum Error {
     NotFound { msg: String }, // Error variant for not found
     InvalidInput { msg: String }, // Error variant for invalid input
+    RateLimited { retry_after_secs: u64 }, // Error variant for a caller exceeding the rate limit
 }
 
 // Helper method to get a book by ID, used in get_book and update_book